@@ -0,0 +1,89 @@
+const FLAP_BYTES: &[u8] = include_bytes!("../resources/sfx/flap.wav");
+const SCORE_BYTES: &[u8] = include_bytes!("../resources/sfx/score.wav");
+const CRASH_BYTES: &[u8] = include_bytes!("../resources/sfx/crash.wav");
+
+pub enum Sfx {
+    Flap,
+    Score,
+    Crash,
+}
+
+impl Sfx {
+    fn bytes(&self) -> &'static [u8] {
+        match self {
+            Sfx::Flap => FLAP_BYTES,
+            Sfx::Score => SCORE_BYTES,
+            Sfx::Crash => CRASH_BYTES,
+        }
+    }
+}
+
+// rodio/cpal don't build for wasm32-unknown-unknown without extra
+// target-specific wiring, so the backend is split the same way `save.rs`
+// splits its storage backend: a real desktop implementation, and a no-op
+// stand-in for the web build.
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use super::Sfx;
+    use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+    use std::io::Cursor;
+
+    /// Thin wrapper around a rodio output stream. `handle` is `None` when
+    /// there's no output device available, in which case `play` is a silent
+    /// no-op instead of an error.
+    pub struct Audio {
+        _stream: Option<OutputStream>,
+        handle: Option<OutputStreamHandle>,
+    }
+
+    impl Audio {
+        pub fn new() -> Self {
+            match OutputStream::try_default() {
+                Ok((stream, handle)) => Self {
+                    _stream: Some(stream),
+                    handle: Some(handle),
+                },
+                Err(_) => Self {
+                    _stream: None,
+                    handle: None,
+                },
+            }
+        }
+
+        pub fn play(&self, sfx: Sfx) {
+            let handle = match &self.handle {
+                Some(handle) => handle,
+                None => return,
+            };
+
+            let sink = match Sink::try_new(handle) {
+                Ok(sink) => sink,
+                Err(_) => return,
+            };
+
+            if let Ok(source) = Decoder::new(Cursor::new(sfx.bytes())) {
+                sink.append(source);
+                sink.detach();
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use super::Sfx;
+
+    /// No rodio/cpal output on wasm32, so the web build just drops every
+    /// cue instead of failing to compile.
+    pub struct Audio;
+
+    impl Audio {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn play(&self, _sfx: Sfx) {}
+    }
+}
+
+pub use backend::Audio;
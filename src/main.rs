@@ -1,5 +1,10 @@
 use bracket_lib::prelude::*;
 
+mod audio;
+mod save;
+
+use audio::{Audio, Sfx};
+
 enum GameMode {
     Menu,
     Playing,
@@ -11,12 +16,19 @@ const VELOCITY_FLAP: f32 = -15.0;
 const SCREEN_WIDTH: i32 = 80;
 const SCREEN_HEIGHT: i32 = 50;
 const PLAYER_OFFSET: i32 = 5;
+const FRAME_DURATION: f32 = 75.0;
+const DRAGON_FRAMES: [u16; 6] = [64, 1, 2, 3, 2, 1];
+const MAX_OBSTACLES: usize = 3;
+const OBSTACLE_SPACING: i32 = SCREEN_WIDTH / 2;
 
 struct Player {
     x: f32,
     y: f32,
     dx: f32,
     dy: f32,
+    prev_x: f32,
+    frame: usize,
+    frame_time: f32,
 }
 
 impl Player {
@@ -26,6 +38,9 @@ impl Player {
             y,
             dx: 15.0,
             dy: 0.0,
+            prev_x: x,
+            frame: 0,
+            frame_time: 0.0,
         }
     }
 
@@ -37,11 +52,46 @@ impl Player {
         self.x.round() as i32
     }
 
+    fn prev_world_x(&self) -> i32 {
+        self.prev_x.round() as i32
+    }
+
     fn render(&mut self, ctx: &mut BTerm) {
-        ctx.set(PLAYER_OFFSET, self.screen_y(), YELLOW, BLACK, to_cp437('@'));
+        // animation timing runs off the wall-clock frame time, independent of
+        // the physics delta used in `update`, so the flap cycle stays smooth
+        // even if the simulation step changes
+        self.frame_time += ctx.frame_time_ms;
+        if self.frame_time > FRAME_DURATION {
+            self.frame_time = 0.0;
+            self.frame = (self.frame + 1) % DRAGON_FRAMES.len();
+        }
+
+        // the base console is a plain cp437 grid (no fancy/sprite console is
+        // registered in `main`), so the animation just cycles the glyph
+        // drawn at the player's cell rather than targeting a second layer
+        ctx.set(
+            PLAYER_OFFSET,
+            self.screen_y(),
+            YELLOW,
+            BLACK,
+            DRAGON_FRAMES[self.frame],
+        );
+    }
+
+    /// Sweeps from last frame's world-x to this frame's, instead of a
+    /// unit-width cell at the current position, so a large `dx` or a frame
+    /// time spike can't carry the player past an obstacle column without
+    /// the rects ever overlapping.
+    fn collision_rect(&self) -> Rect {
+        let x0 = self.prev_world_x().min(self.world_x());
+        let x1 = self.prev_world_x().max(self.world_x()) + 1;
+
+        Rect::new(x0, self.screen_y(), x1, self.screen_y() + 1)
     }
 
     fn update(&mut self, delta_s: f32) {
+        self.prev_x = self.x;
+
         // gravity
         self.dy += ACCEL_GRAVITY * delta_s;
 
@@ -58,10 +108,46 @@ impl Player {
     }
 }
 
+#[derive(Copy, Clone)]
+struct Rect {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl Rect {
+    fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self { x0, y0, x1, y1 }
+    }
+
+    fn collides(&self, other: &Rect) -> bool {
+        self.x0 < other.x1 && self.x1 > other.x0 && self.y0 < other.y1 && self.y1 > other.y0
+    }
+
+    /// Translates this rect from world-x to screen-x, the way `Obstacle::render`
+    /// does, so the debug overlay can draw it where the player actually sees it.
+    fn to_screen(&self, player_x: i32) -> Rect {
+        Rect::new(
+            self.x0 - player_x + PLAYER_OFFSET,
+            self.y0,
+            self.x1 - player_x + PLAYER_OFFSET,
+            self.y1,
+        )
+    }
+
+    fn render_debug(&self, ctx: &mut BTerm, color: RGB) {
+        let width = (self.x1 - self.x0).max(1) - 1;
+        let height = (self.y1 - self.y0).max(1) - 1;
+        ctx.draw_hollow_box(self.x0, self.y0, width, height, color, BLACK);
+    }
+}
+
 struct Obstacle {
     x: i32,
     gap_y: i32,
     size: i32,
+    passed: bool,
 }
 
 impl Obstacle {
@@ -72,6 +158,7 @@ impl Obstacle {
             x,
             gap_y: random.range(10, 40),
             size: i32::max(2, 20 - score),
+            passed: false,
         }
     }
 
@@ -90,78 +177,153 @@ impl Obstacle {
         }
     }
 
-    fn hit(&mut self, player: &Player) -> bool {
+    fn collision_rects(&self) -> (Rect, Rect) {
         let half_size = self.size / 2;
-        let does_x_match = player.world_x() == self.x;
-        let player_above_gap = player.screen_y() < self.gap_y - half_size;
-        let player_below_gap = player.screen_y() > self.gap_y + half_size;
+        let top_rect = Rect::new(self.x, 0, self.x + 1, self.gap_y - half_size);
+        let bottom_rect = Rect::new(self.x, self.gap_y + half_size, self.x + 1, SCREEN_HEIGHT);
+
+        (top_rect, bottom_rect)
+    }
+
+    fn hit(&mut self, player: &Player) -> bool {
+        let player_rect = player.collision_rect();
+        let (top_rect, bottom_rect) = self.collision_rects();
 
-        does_x_match && (player_above_gap || player_below_gap)
+        player_rect.collides(&top_rect) || player_rect.collides(&bottom_rect)
     }
 }
 
 struct State {
     player: Player,
-    obstacle: Obstacle,
+    obstacles: Vec<Obstacle>,
     mode: GameMode,
     score: i32,
-    frame_time: f32,
+    best_score: i32,
+    audio: Audio,
+    debug: bool,
 }
 
 impl State {
     fn new() -> Self {
         Self {
             player: Player::new(PLAYER_OFFSET as f32, SCREEN_HEIGHT as f32 / 2.0),
-            obstacle: Obstacle::new(SCREEN_WIDTH, 0),
+            obstacles: Self::spawn_obstacles(SCREEN_WIDTH, 0),
             mode: GameMode::Menu,
             score: 0,
-            frame_time: 0.0,
+            best_score: save::load_best_score(),
+            audio: Audio::new(),
+            debug: false,
         }
     }
 
+    fn spawn_obstacles(start_x: i32, score: i32) -> Vec<Obstacle> {
+        (0..MAX_OBSTACLES as i32)
+            .map(|i| Obstacle::new(start_x + i * OBSTACLE_SPACING, score))
+            .collect()
+    }
+
     fn play(&mut self, ctx: &mut BTerm) {
         ctx.cls_bg(NAVY);
 
-        // self.frame_time += ctx.frame_time_ms;
-        // if self.frame_time > FRAME_DURATION {
-        //     self.frame_time = 0.0;
-        //     self.player.update(ctx.frame_time_ms as f32 / 1000.0);
-        // }
-
         let delta_s = ctx.frame_time_ms as f32 / 1000.0;
         self.player.update(delta_s);
 
         if let Some(VirtualKeyCode::Space) = ctx.key {
             self.player.flap();
+            self.audio.play(Sfx::Flap);
         }
 
         self.player.render(ctx);
-        self.obstacle.render(ctx, self.player.world_x());
+
+        let player_x = self.player.world_x();
+        let mut hit = false;
+
+        for obstacle in self.obstacles.iter_mut() {
+            obstacle.render(ctx, player_x);
+
+            if obstacle.hit(&self.player) {
+                hit = true;
+            }
+
+            if !obstacle.passed && player_x > obstacle.x + PLAYER_OFFSET {
+                obstacle.passed = true;
+                self.score += 1;
+                self.audio.play(Sfx::Score);
+            }
+        }
 
         ctx.print(0, 0, "Press SPACE to flap.");
         ctx.print(0, 1, &format!("Score {}", self.score));
 
-        if self.player.world_x() > self.obstacle.x + PLAYER_OFFSET {
-            self.score += 1;
-            self.obstacle = Obstacle::new(self.player.world_x() + SCREEN_WIDTH - PLAYER_OFFSET, self.score);
+        // drop obstacles that have scrolled off the left edge, then top the
+        // pool back up so MAX_OBSTACLES are always queued up ahead
+        self.obstacles
+            .retain(|obstacle| obstacle.x - player_x + PLAYER_OFFSET >= 0);
+
+        while self.obstacles.len() < MAX_OBSTACLES {
+            let spawn_x = self
+                .obstacles
+                .last()
+                .map_or(player_x + SCREEN_WIDTH, |last| last.x + OBSTACLE_SPACING);
+            self.obstacles.push(Obstacle::new(spawn_x, self.score));
+        }
+
+        if self.debug {
+            self.render_debug_overlay(ctx, player_x);
         }
 
-        if self.player.screen_y() > SCREEN_HEIGHT || self.obstacle.hit(&self.player) {
+        if self.player.screen_y() > SCREEN_HEIGHT || hit {
+            self.audio.play(Sfx::Crash);
+            self.best_score = self.best_score.max(self.score);
+            save::save_best_score(self.best_score);
             self.mode = GameMode::End;
         }
     }
 
+    fn render_debug_overlay(&self, ctx: &mut BTerm, player_x: i32) {
+        ctx.print(0, SCREEN_HEIGHT - 5, "-- DEBUG (F1) --");
+        ctx.print(
+            0,
+            SCREEN_HEIGHT - 4,
+            &format!(
+                "x {:.1} y {:.1} dx {:.1} dy {:.1}",
+                self.player.x, self.player.y, self.player.dx, self.player.dy
+            ),
+        );
+        ctx.print(
+            0,
+            SCREEN_HEIGHT - 3,
+            &format!("frame_time {:.1}ms", ctx.frame_time_ms),
+        );
+        ctx.print(
+            0,
+            SCREEN_HEIGHT - 2,
+            &format!("obstacles {}", self.obstacles.len()),
+        );
+
+        self.player
+            .collision_rect()
+            .to_screen(player_x)
+            .render_debug(ctx, MAGENTA);
+
+        for obstacle in &self.obstacles {
+            let (top_rect, bottom_rect) = obstacle.collision_rects();
+            top_rect.to_screen(player_x).render_debug(ctx, MAGENTA);
+            bottom_rect.to_screen(player_x).render_debug(ctx, MAGENTA);
+        }
+    }
+
     fn restart(&mut self) {
         self.player = Player::new(PLAYER_OFFSET as f32, SCREEN_HEIGHT as f32 / 2.0);
-        self.obstacle = Obstacle::new(SCREEN_WIDTH, 0);
+        self.obstacles = Self::spawn_obstacles(SCREEN_WIDTH, 0);
         self.score = 0;
-        self.frame_time = 0.0;
         self.mode = GameMode::Playing;
     }
 
     fn main_menu(&mut self, ctx: &mut BTerm) {
         ctx.cls();
         ctx.print_centered(5, "Welcome to Flappy Dragon");
+        ctx.print_centered(6, &format!("Best {}", self.best_score));
         ctx.print_centered(8, "(P) Play game");
         ctx.print_centered(9, "(Q) Quit game");
 
@@ -177,9 +339,10 @@ impl State {
     fn dead(&mut self, ctx: &mut BTerm) {
         ctx.cls();
         ctx.print_centered(5, "You are dead!");
-        ctx.print_centered(6, &format!("You earned {} points", self.score));
-        ctx.print_centered(8, "(P) Play again");
-        ctx.print_centered(9, "(Q) Quit game");
+        ctx.print_centered(6, &format!("Score {}", self.score));
+        ctx.print_centered(7, &format!("Best {}", self.best_score));
+        ctx.print_centered(9, "(P) Play again");
+        ctx.print_centered(10, "(Q) Quit game");
 
         if let Some(key) = ctx.key {
             match key {
@@ -193,6 +356,10 @@ impl State {
 
 impl GameState for State {
     fn tick(&mut self, ctx: &mut BTerm) {
+        if let Some(VirtualKeyCode::F1) = ctx.key {
+            self.debug = !self.debug;
+        }
+
         match self.mode {
             GameMode::Menu => self.main_menu(ctx),
             GameMode::Playing => self.play(ctx),
@@ -205,6 +372,24 @@ fn main() -> BError {
     let context = BTermBuilder::simple80x50()
         .with_title("Flappy Dragon")
         .build()?;
-    
+
     main_loop(context, State::new())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_registers_when_the_player_jumps_past_the_column_in_one_frame() {
+        let mut player = Player::new(79.0, 25.0);
+        player.prev_x = 79.0;
+        player.x = 82.0;
+
+        let mut obstacle = Obstacle::new(80, 0);
+        obstacle.gap_y = 10;
+        obstacle.size = 2;
+
+        assert!(obstacle.hit(&player));
+    }
+}
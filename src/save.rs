@@ -0,0 +1,51 @@
+//! Persists the player's best score across runs. Desktop targets use a small
+//! save file next to the executable; WASM targets have no real filesystem,
+//! so they fall back to browser `localStorage` instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::fs;
+
+    const SAVE_PATH: &str = "flappy_dragon_best.txt";
+
+    pub fn load_best() -> i32 {
+        fs::read_to_string(SAVE_PATH)
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn save_best(score: i32) {
+        let _ = fs::write(SAVE_PATH, score.to_string());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    const STORAGE_KEY: &str = "flappy_dragon_best";
+
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn load_best() -> i32 {
+        storage()
+            .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0)
+    }
+
+    pub fn save_best(score: i32) {
+        if let Some(storage) = storage() {
+            let _ = storage.set_item(STORAGE_KEY, &score.to_string());
+        }
+    }
+}
+
+pub fn load_best_score() -> i32 {
+    backend::load_best()
+}
+
+pub fn save_best_score(score: i32) {
+    backend::save_best(score);
+}